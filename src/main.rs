@@ -1,13 +1,22 @@
-use std::{collections::HashSet, path::PathBuf, time::Instant};
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::PathBuf,
+    str::FromStr,
+    time::Instant,
+};
 
 use anyhow::Context;
-use cascade::cascade;
 use gridly::prelude::*;
 use image::{io::Reader as ImageReader, GenericImageView, Rgba};
 use mimalloc::MiMalloc;
 use rayon::prelude::*;
 use structopt::StructOpt;
 
+mod writer;
+
+use writer::OutputFormat;
+
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
@@ -23,9 +32,9 @@ fn from_index(index: usize, dimensions: Vector) -> Location {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct PixelPair {
-    origin: Rgba<u8>,
-    neighbor: Rgba<u8>,
+pub(crate) struct PixelPair {
+    pub(crate) origin: Rgba<u8>,
+    pub(crate) neighbor: Rgba<u8>,
 }
 
 impl PixelPair {
@@ -67,18 +76,35 @@ impl GridBounds for Rectangle {
     }
 }
 
-/// Run the couladj algorithm, using rayon for multithreading.
+/// Sum two `PixelPair` occurrence counts together, key by key. Used as the
+/// `reduce` step for every adjacency kernel below, since they all shard
+/// their work across rayon threads and need to merge per-thread maps back
+/// into one.
+fn merge_counts(
+    mut map1: HashMap<PixelPair, u64>,
+    map2: HashMap<PixelPair, u64>,
+) -> HashMap<PixelPair, u64> {
+    for (pair, count) in map2 {
+        *map1.entry(pair).or_insert(0) += count;
+    }
+    map1
+}
+
+/// Like `couladj_generic_rayon`, but only pixels whose row falls in
+/// `origin_rows` are treated as origins. Rows outside that range still
+/// participate as neighbor lookups (and are still bounds-checked via
+/// `rect`), but never start a pair themselves.
 ///
-/// `buffer` is a 2D buffer of pixels, flattened in row- or column- major order.
-/// `dimensions` is the dimensions of the original image
-/// `adjacencies` is the directions that are checked per-pixel. For example,
-/// when checking 4-way adjacencies, it might be `[(0, 1), (1, 0), (-1, 0), (0, -1)]`
-#[inline]
-fn couladj_generic_rayon(
+/// This lets a caller hand in a buffer strip with a trailing halo of
+/// extra rows, to detect adjacencies that reach past the strip's own
+/// bottom edge, without double-counting the halo rows' own outgoing
+/// adjacencies when the next strip reprocesses them as core rows.
+fn couladj_strip_rayon(
     buffer: &[Rgba<u8>],
     dimensions: Vector,
     adjacencies: &[Vector],
-) -> HashSet<PixelPair> {
+    origin_rows: Range<isize>,
+) -> HashMap<PixelPair, u64> {
     let rect = Rectangle { dimensions };
     buffer
         // For each pixel in the buffer...
@@ -87,6 +113,9 @@ fn couladj_generic_rayon(
         .enumerate()
         // Compute the coordinates of the pixel, based on the index
         .map(|(index, pixel)| (from_index(index, rect.dimensions), pixel))
+        // Skip pixels outside the origin range; they only ever serve as
+        // neighbor lookups below, never as a pair's origin.
+        .filter(|(location, _)| origin_rows.contains(&location.row.0))
         // Process each pixel
         .flat_map_iter(|(location, pixel)| {
             adjacencies
@@ -109,16 +138,327 @@ fn couladj_generic_rayon(
                     neighbor,
                 })
         })
-        // Collect all the pixel pairs into a HashMap. This runs once
-        // for each thread
-        .fold(HashSet::new, |set, pair| cascade!(set; ..insert(pair);))
-        // Merge all the HashMaps together
-        .reduce(HashSet::new, |set1, set2| {
-            match set1.capacity() > set2.capacity() {
-                true => cascade!(set1; ..extend(set2);),
-                false => cascade!(set2; ..extend(set1);),
+        // Count occurrences of each pixel pair into a HashMap. This runs
+        // once for each thread
+        .fold(HashMap::new, |mut map, pair| {
+            *map.entry(pair).or_insert(0u64) += 1;
+            map
+        })
+        // Merge all the HashMaps together, summing counts for matching pairs
+        .reduce(HashMap::new, merge_counts)
+}
+
+/// Run the couladj algorithm, using rayon for multithreading.
+///
+/// `buffer` is a 2D buffer of pixels, flattened in row- or column- major order.
+/// `dimensions` is the dimensions of the original image
+/// `adjacencies` is the directions that are checked per-pixel. For example,
+/// when checking 4-way adjacencies, it might be `[(0, 1), (1, 0), (-1, 0), (0, -1)]`
+///
+/// This is just `couladj_strip_rayon` over the whole buffer as a single
+/// strip, with every row treated as an origin row.
+#[inline]
+fn couladj_generic_rayon(
+    buffer: &[Rgba<u8>],
+    dimensions: Vector,
+    adjacencies: &[Vector],
+) -> HashMap<PixelPair, u64> {
+    couladj_strip_rayon(buffer, dimensions, adjacencies, 0..dimensions.rows.0)
+}
+
+/// Process `buffer` in horizontal strips of `tile_rows` rows at a time,
+/// each carrying a trailing halo of `radius` extra rows so that
+/// adjacencies reaching across a strip boundary are still detected,
+/// merging every strip's result into one running `HashMap`.
+///
+/// Because each strip (plus halo) is a small, bounded slice of the full
+/// buffer, this keeps the kernel's own working set bounded regardless of
+/// the image's total size.
+fn couladj_tiled_rayon(
+    buffer: &[Rgba<u8>],
+    dimensions: Vector,
+    adjacencies: &[Vector],
+    tile_rows: usize,
+    radius: usize,
+) -> HashMap<PixelPair, u64> {
+    let total_rows = dimensions.rows.0 as usize;
+    let columns = dimensions.columns.0 as usize;
+    let tile_rows = tile_rows.max(1);
+
+    (0..total_rows)
+        .step_by(tile_rows)
+        .collect::<Vec<usize>>()
+        .into_par_iter()
+        .map(|start_row| {
+            let origin_rows = tile_rows.min(total_rows - start_row);
+            let halo_rows = radius.min(total_rows - start_row - origin_rows);
+            let strip_rows = origin_rows + halo_rows;
+
+            let strip_start = start_row * columns;
+            let strip_end = strip_start + strip_rows * columns;
+
+            let strip_dimensions = Vector {
+                rows: Rows(strip_rows as isize),
+                columns: dimensions.columns,
+            };
+
+            couladj_strip_rayon(
+                &buffer[strip_start..strip_end],
+                strip_dimensions,
+                adjacencies,
+                0..origin_rows as isize,
+            )
+        })
+        .reduce(HashMap::new, merge_counts)
+}
+
+/// Linearize a single sRGB channel (in `[0, 1]`) by undoing the sRGB
+/// transfer function.
+fn srgb_to_linear(c: f32) -> f32 {
+    match c <= 0.04045 {
+        true => c / 12.92,
+        false => ((c + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+/// The CIELAB `f` helper, as defined by the CIE standard.
+fn lab_f(t: f32) -> f32 {
+    match t > 0.008856 {
+        true => t.powf(1.0 / 3.0),
+        false => 7.787 * t + 16.0 / 116.0,
+    }
+}
+
+/// Convert an sRGB pixel to CIELAB (D65 white point), ignoring alpha.
+fn to_lab(pixel: Rgba<u8>) -> [f32; 3] {
+    let [r, g, b] = [pixel.0[0], pixel.0[1], pixel.0[2]]
+        .map(|channel| srgb_to_linear(channel as f32 / 255.0));
+
+    let x = r * 0.4124564 + g * 0.3575761 + b * 0.1804375;
+    let y = r * 0.2126729 + g * 0.7151522 + b * 0.0721750;
+    let z = r * 0.0193339 + g * 0.119192 + b * 0.9503041;
+
+    const XN: f32 = 0.95047;
+    const YN: f32 = 1.0;
+    const ZN: f32 = 1.08883;
+
+    let (fx, fy, fz) = (lab_f(x / XN), lab_f(y / YN), lab_f(z / ZN));
+
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// Follow `parent` union-find links to `node`'s root, path-compressing
+/// along the way.
+fn find_root(parent: &mut [usize], mut node: usize) -> usize {
+    while parent[node] != node {
+        parent[node] = parent[parent[node]];
+        node = parent[node];
+    }
+    node
+}
+
+/// Build a map from every color present in `buffer` to a single canonical
+/// representative color for its perceptual tolerance class, so that nearby
+/// colors (antialiasing, JPEG noise, gradients) collapse onto the same
+/// `Rgba<u8>` before adjacencies are computed.
+///
+/// This unions the distinct colors actually present in `buffer` directly by
+/// their CIE76 Delta-E distance, rather than snapping each color onto a
+/// fixed Lab quantization grid: a grid has hard cell boundaries, and common
+/// values like pure white (`L* = 100`) or zero-chroma grays (`a* = b* = 0`)
+/// land exactly on one, which would systematically split colors well within
+/// `tolerance` of each other into different cells.
+fn build_color_classes(buffer: &[Rgba<u8>], tolerance: f32) -> HashMap<Rgba<u8>, Rgba<u8>> {
+    let mut unique: Vec<Rgba<u8>> = Vec::new();
+    let mut indices: HashMap<Rgba<u8>, usize> = HashMap::new();
+
+    for &pixel in buffer {
+        indices.entry(pixel).or_insert_with(|| {
+            unique.push(pixel);
+            unique.len() - 1
+        });
+    }
+
+    let labs: Vec<[f32; 3]> = unique.iter().map(|&pixel| to_lab(pixel)).collect();
+    let mut parent: Vec<usize> = (0..unique.len()).collect();
+    let tolerance_sq = tolerance * tolerance;
+
+    for i in 0..unique.len() {
+        for j in (i + 1)..unique.len() {
+            let [l1, a1, b1] = labs[i];
+            let [l2, a2, b2] = labs[j];
+            let distance_sq = (l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2);
+
+            if distance_sq <= tolerance_sq {
+                let (root_i, root_j) = (find_root(&mut parent, i), find_root(&mut parent, j));
+                if root_i != root_j {
+                    parent[root_i] = root_j;
+                }
             }
+        }
+    }
+
+    indices
+        .into_iter()
+        .map(|(pixel, index)| (pixel, unique[find_root(&mut parent, index)]))
+        .collect()
+}
+
+/// A sentinel label meaning "not yet visited by the flood fill".
+const UNLABELED: u32 = u32::MAX;
+
+/// Segment `buffer` into connected regions of identical color via an
+/// iterative flood fill, then report adjacency between *regions* rather
+/// than between raw pixels.
+///
+/// Regions are discovered with a full (bidirectional) neighborhood, built
+/// from `adjacencies` and its mirror image, so that a region is correctly
+/// identified regardless of which direction it was first entered from.
+/// The reported `PixelPair`s, however, are one-directional over
+/// `adjacencies`, matching the convention used by `couladj_generic_rayon`.
+fn couladj_regions_rayon(
+    buffer: &[Rgba<u8>],
+    dimensions: Vector,
+    adjacencies: &[Vector],
+) -> HashMap<PixelPair, u64> {
+    let rect = Rectangle { dimensions };
+
+    let fill_adjacencies: Vec<Vector> = adjacencies
+        .iter()
+        .copied()
+        .flat_map(|delta| {
+            let reverse = Vector {
+                rows: Rows(-delta.rows.0),
+                columns: Columns(-delta.columns.0),
+            };
+            [delta, reverse]
         })
+        .collect();
+
+    let mut labels = vec![UNLABELED; buffer.len()];
+    let mut representatives: Vec<Rgba<u8>> = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+
+    for start in 0..buffer.len() {
+        if labels[start] != UNLABELED {
+            continue;
+        }
+
+        let region = representatives.len() as u32;
+        let seed_color = buffer[start];
+        representatives.push(seed_color);
+
+        labels[start] = region;
+        stack.push(start);
+
+        while let Some(index) = stack.pop() {
+            let location = from_index(index, rect.dimensions);
+
+            for &delta in &fill_adjacencies {
+                let neighbor_coords = location + delta;
+                if !rect.location_in_bounds(neighbor_coords) {
+                    continue;
+                }
+
+                let neighbor_index = to_index(neighbor_coords, rect.dimensions);
+                if labels[neighbor_index] == UNLABELED && buffer[neighbor_index] == seed_color {
+                    labels[neighbor_index] = region;
+                    stack.push(neighbor_index);
+                }
+            }
+        }
+    }
+
+    (0..buffer.len())
+        .into_par_iter()
+        .flat_map_iter(|index| {
+            let location = from_index(index, rect.dimensions);
+            adjacencies
+                .iter()
+                .copied()
+                .map(move |delta| location + delta)
+                .filter(|neighbor_coords| rect.location_in_bounds(neighbor_coords))
+                .map(move |neighbor_coords| (index, to_index(neighbor_coords, rect.dimensions)))
+        })
+        .filter(|&(index, neighbor_index)| labels[index] != labels[neighbor_index])
+        .map(|(index, neighbor_index)| PixelPair {
+            origin: representatives[labels[index] as usize],
+            neighbor: representatives[labels[neighbor_index] as usize],
+        })
+        .fold(HashMap::new, |mut map, pair| {
+            *map.entry(pair).or_insert(0u64) += 1;
+            map
+        })
+        .reduce(HashMap::new, merge_counts)
+}
+
+/// How far apart two pixels may be and still count as "neighbors".
+#[derive(Debug, Clone, Copy)]
+enum Connectivity {
+    /// Manhattan distance: `|dr| + |dc| <= radius`
+    Four,
+    /// Chebyshev distance: `max(|dr|, |dc|) <= radius`
+    Eight,
+}
+
+impl FromStr for Connectivity {
+    type Err = String;
+
+    fn from_str(connectivity: &str) -> Result<Self, Self::Err> {
+        match connectivity {
+            "4" => Ok(Connectivity::Four),
+            "8" => Ok(Connectivity::Eight),
+            other => Err(format!(
+                "unrecognized connectivity {other:?}; expected \"4\" or \"8\""
+            )),
+        }
+    }
+}
+
+/// A neighborhood radius, guaranteed to be at least 1 (a radius of 0 would
+/// silently produce an empty adjacency list).
+#[derive(Debug, Clone, Copy)]
+struct Radius(u32);
+
+impl FromStr for Radius {
+    type Err = String;
+
+    fn from_str(radius: &str) -> Result<Self, Self::Err> {
+        let radius: u32 = radius
+            .parse()
+            .map_err(|_| format!("radius must be a non-negative integer, got {radius:?}"))?;
+
+        match radius {
+            0 => Err("radius must be at least 1".to_string()),
+            radius => Ok(Radius(radius)),
+        }
+    }
+}
+
+/// Enumerate the one-directional adjacency offsets for a given
+/// neighborhood `radius` and `connectivity`.
+///
+/// `radius=1, connectivity=Four` reproduces the original cardinal-direction
+/// offsets; `radius=1, connectivity=Eight` reproduces the old
+/// `--full-adjacencies` offsets. Only one offset of each +/- pair is kept,
+/// since `couladj_generic_rayon`'s 1-way scan becomes bidirectional again
+/// via `pair.swap()`.
+fn adjacency_offsets(radius: Radius, connectivity: Connectivity) -> Vec<Vector> {
+    let radius = radius.0 as i32;
+
+    (-radius..=radius)
+        .flat_map(|dr| (-radius..=radius).map(move |dc| (dr, dc)))
+        // Drop the origin itself, and keep only one of each +/- pair.
+        .filter(|&(dr, dc)| dr > 0 || (dr == 0 && dc > 0))
+        .filter(|&(dr, dc)| match connectivity {
+            Connectivity::Four => dr.abs() + dc.abs() <= radius,
+            Connectivity::Eight => dr.abs().max(dc.abs()) <= radius,
+        })
+        .map(|(dr, dc)| Vector {
+            rows: Rows(dr as isize),
+            columns: Columns(dc as isize),
+        })
+        .collect()
 }
 
 #[derive(Debug, StructOpt)]
@@ -127,11 +467,18 @@ struct Args {
     file: PathBuf,
 
     #[structopt(
-        short = "a",
         long,
-        help = "If given, adjacencies will be computed for all 8 directions, rather than the 4 cardinal directions"
+        default_value = "1",
+        help = "The neighborhood radius to check for adjacencies; must be at least 1"
+    )]
+    radius: Radius,
+
+    #[structopt(
+        long,
+        default_value = "4",
+        help = "Neighbor connectivity to use within the radius: \"4\" (Manhattan) or \"8\" (Chebyshev)"
     )]
-    full_adjacencies: bool,
+    connectivity: Connectivity,
 
     #[structopt(
         short,
@@ -139,11 +486,54 @@ struct Args {
         help = "Instead of a tsv, just input the number of unique pairs"
     )]
     count: bool,
+
+    #[structopt(
+        short,
+        long,
+        help = "Segment the image into connected color regions first, and report adjacency between regions rather than individual pixels"
+    )]
+    regions: bool,
+
+    #[structopt(
+        short,
+        long,
+        help = "Merge colors within this CIE76 Delta-E distance of each other, so antialiasing and compression noise don't explode the output"
+    )]
+    tolerance: Option<f32>,
+
+    #[structopt(
+        long,
+        default_value = "tsv",
+        help = "The format to write adjacencies in: \"tsv\" or \"dot\""
+    )]
+    output_format: OutputFormat,
+
+    #[structopt(
+        short,
+        long,
+        help = "Count occurrences of each adjacency, append a count column to the TSV output (the dot format has no count), and sort by descending count"
+    )]
+    weighted: bool,
+
+    #[structopt(
+        long,
+        help = "Process the already-decoded image in horizontal strips of this many rows at a time, bounding the adjacency kernel's own working set (the image itself is still fully decoded and held in memory up front)"
+    )]
+    tile_rows: Option<usize>,
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::from_args();
 
+    if args.regions && args.tile_rows.is_some() {
+        anyhow::bail!(
+            "--tile-rows is not supported together with --regions; region flood fill needs the whole image at once"
+        );
+    }
+
+    // NOTE: this always decodes the whole image into memory up front; there's
+    // no lazy/mmap-backed decode path yet. `--tile-rows` only bounds the
+    // adjacency kernel's own working set once this buffer already exists.
     eprintln!("Loading image...");
     let now = Instant::now();
     let (dimensions, buffer) = {
@@ -164,25 +554,40 @@ fn main() -> anyhow::Result<()> {
     };
     eprintln!("  {:?}", now.elapsed());
 
+    let buffer = match args.tolerance {
+        None => buffer,
+        Some(tolerance) => {
+            eprintln!("Merging perceptually similar colors...");
+            let now = Instant::now();
+            let classes = build_color_classes(&buffer, tolerance);
+            let merged = buffer.iter().map(|pixel| classes[pixel]).collect();
+            eprintln!("  {:?}", now.elapsed());
+            merged
+        }
+    };
+
     eprintln!("Calculating adjacencies...");
     let now = Instant::now();
-    let mut result = match args.full_adjacencies {
-        false => couladj_generic_rayon(&buffer, dimensions, &[Down.as_vector(), Right.as_vector()]),
-        true => couladj_generic_rayon(
+    let adjacencies = adjacency_offsets(args.radius, args.connectivity);
+    let result = match (args.regions, args.tile_rows) {
+        (true, _) => couladj_regions_rayon(&buffer, dimensions, &adjacencies),
+        (false, Some(tile_rows)) => couladj_tiled_rayon(
             &buffer,
             dimensions,
-            &[
-                Down.as_vector(),
-                Right.as_vector(),
-                Down + Left,
-                Down + Right,
-            ],
+            &adjacencies,
+            tile_rows,
+            args.radius.0 as usize,
         ),
+        (false, None) => couladj_generic_rayon(&buffer, dimensions, &adjacencies),
     };
     eprintln!("  {:?}", now.elapsed());
 
     // We only search for 1-way adjacencies; make sure our set is bidirectional
-    result.extend(result.clone().iter().map(|pair| pair.swap()));
+    let swapped = result
+        .iter()
+        .map(|(&pair, &count)| (pair.swap(), count))
+        .collect();
+    let result = merge_counts(result, swapped);
 
     if args.count {
         println!("Found {} unique adjacencies", result.len())
@@ -190,28 +595,127 @@ fn main() -> anyhow::Result<()> {
         eprintln!("Sorting adjacencies...");
         let now = Instant::now();
         let data = {
-            let mut data: Vec<PixelPair> = result.iter().copied().collect();
-            data.sort_unstable();
+            let mut data: Vec<(PixelPair, u64)> = result.into_iter().collect();
+            match args.weighted {
+                true => data.sort_unstable_by_key(|&(pair, count)| (std::cmp::Reverse(count), pair)),
+                false => data.sort_unstable_by_key(|&(pair, _)| pair),
+            }
             data
         };
         eprintln!("  {:?}", now.elapsed());
 
-        println!("r\tg\tb\ta\tadj_r\tadj_g\tadj_b\tadj_a");
-
-        data.iter().for_each(|pair| {
-            println!(
-                "{r}\t{g}\t{b}\t{a}\t{nr}\t{ng}\t{nb}\t{na}",
-                r = pair.origin.0[0],
-                g = pair.origin.0[1],
-                b = pair.origin.0[2],
-                a = pair.origin.0[3],
-                nr = pair.neighbor.0[0],
-                ng = pair.neighbor.0[1],
-                nb = pair.neighbor.0[2],
-                na = pair.neighbor.0[3],
-            )
-        });
+        let stdout = std::io::stdout();
+        args.output_format
+            .writer(args.weighted)
+            .write(&mut stdout.lock(), &data)
+            .context("Failed to write adjacencies to stdout")?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_flood_fill_merges_same_color_runs() {
+        // A 2x2 image: the top row is all A, the bottom row is all B.
+        let a = Rgba([1, 1, 1, 255]);
+        let b = Rgba([2, 2, 2, 255]);
+        let buffer = [a, a, b, b];
+        let dimensions = Vector {
+            rows: Rows(2),
+            columns: Columns(2),
+        };
+        let adjacencies = [Down.as_vector(), Right.as_vector()];
+
+        let result = couladj_regions_rayon(&buffer, dimensions, &adjacencies);
+
+        // Each column contributes one Down adjacency from the A region
+        // into the B region; the Right adjacencies within each row are
+        // within a single region and shouldn't appear.
+        let mut expected = HashMap::new();
+        expected.insert(PixelPair { origin: a, neighbor: b }, 2);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn lab_white_and_black_land_at_the_expected_extremes() {
+        let [l, a, b] = to_lab(Rgba([255, 255, 255, 255]));
+        assert!((l - 100.0).abs() < 0.1, "L* of white should be ~100, got {l}");
+        assert!(a.abs() < 0.1, "a* of white should be ~0, got {a}");
+        assert!(b.abs() < 0.1, "b* of white should be ~0, got {b}");
+
+        let [l, ..] = to_lab(Rgba([0, 0, 0, 255]));
+        assert!(l.abs() < 0.1, "L* of black should be ~0, got {l}");
+    }
+
+    #[test]
+    fn tolerance_merges_nearby_colors_but_keeps_distant_ones_separate() {
+        let near_white = Rgba([255, 255, 255, 255]);
+        let almost_near_white = Rgba([253, 254, 255, 255]);
+        let black = Rgba([0, 0, 0, 255]);
+        let buffer = [near_white, almost_near_white, black];
+
+        let classes = build_color_classes(&buffer, 5.0);
+
+        assert_eq!(classes[&near_white], classes[&almost_near_white]);
+        assert_ne!(classes[&near_white], classes[&black]);
+    }
+
+    fn as_deltas(offsets: &[Vector]) -> Vec<(isize, isize)> {
+        let mut deltas: Vec<(isize, isize)> =
+            offsets.iter().map(|v| (v.rows.0, v.columns.0)).collect();
+        deltas.sort_unstable();
+        deltas
+    }
+
+    #[test]
+    fn radius_1_four_connectivity_matches_original_cardinal_offsets() {
+        let offsets = adjacency_offsets(Radius(1), Connectivity::Four);
+        assert_eq!(as_deltas(&offsets), vec![(0, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn radius_1_eight_connectivity_matches_original_full_adjacencies() {
+        let offsets = adjacency_offsets(Radius(1), Connectivity::Eight);
+        assert_eq!(as_deltas(&offsets), vec![(0, 1), (1, -1), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn radius_2_four_connectivity_excludes_offsets_past_the_manhattan_radius() {
+        let offsets = adjacency_offsets(Radius(2), Connectivity::Four);
+        // (1, 1) has Manhattan distance 2 (included), (1, 2) has distance 3 (excluded)
+        assert!(as_deltas(&offsets).contains(&(1, 1)));
+        assert!(!as_deltas(&offsets).contains(&(1, 2)));
+    }
+
+    #[test]
+    fn radius_zero_is_rejected() {
+        assert!("0".parse::<Radius>().is_err());
+        assert!("1".parse::<Radius>().is_ok());
+    }
+
+    #[test]
+    fn tiled_processing_matches_untiled_processing() {
+        // 5 rows x 4 columns, every pixel a distinct color, so the
+        // resulting adjacencies uniquely pin down which pixels paired up.
+        let dimensions = Vector {
+            rows: Rows(5),
+            columns: Columns(4),
+        };
+        let buffer: Vec<Rgba<u8>> = (0..20)
+            .map(|index: u8| Rgba([index, index, index, 255]))
+            .collect();
+        let adjacencies = adjacency_offsets(Radius(1), Connectivity::Four);
+
+        let whole = couladj_generic_rayon(&buffer, dimensions, &adjacencies);
+
+        // tile_rows=2 doesn't evenly divide 5 rows, and radius=1 is the
+        // minimum halo that still covers the Down offset's reach.
+        let tiled = couladj_tiled_rayon(&buffer, dimensions, &adjacencies, 2, 1);
+
+        assert_eq!(whole, tiled);
+    }
+}
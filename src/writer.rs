@@ -0,0 +1,144 @@
+//! Output formats for a computed set of color adjacencies.
+
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use image::Rgba;
+
+use crate::PixelPair;
+
+/// Render a `PixelPair` data set, along with each pair's occurrence count,
+/// to some output format.
+///
+/// Implementations receive the full, sorted, bidirectional set of pairs
+/// produced by the adjacency kernel; it's up to each writer to decide how
+/// (or whether) to deduplicate the two directions of an edge, and whether
+/// to surface the count at all.
+pub trait AdjacencyWriter {
+    fn write(&self, out: &mut dyn Write, pairs: &[(PixelPair, u64)]) -> io::Result<()>;
+}
+
+/// Format a pixel's color as a `#RRGGBBAA` hex string.
+fn hex_color(color: Rgba<u8>) -> String {
+    format!(
+        "#{:02X}{:02X}{:02X}{:02X}",
+        color.0[0], color.0[1], color.0[2], color.0[3]
+    )
+}
+
+/// The original tab-separated-values format: one row per directed pair.
+///
+/// When `weighted` is set, an extra `count` column reports how many times
+/// each adjacency occurred.
+#[derive(Debug, Clone, Copy)]
+pub struct TsvWriter {
+    pub weighted: bool,
+}
+
+impl AdjacencyWriter for TsvWriter {
+    fn write(&self, out: &mut dyn Write, pairs: &[(PixelPair, u64)]) -> io::Result<()> {
+        match self.weighted {
+            true => writeln!(out, "r\tg\tb\ta\tadj_r\tadj_g\tadj_b\tadj_a\tcount")?,
+            false => writeln!(out, "r\tg\tb\ta\tadj_r\tadj_g\tadj_b\tadj_a")?,
+        }
+
+        for &(pair, count) in pairs {
+            write!(
+                out,
+                "{r}\t{g}\t{b}\t{a}\t{nr}\t{ng}\t{nb}\t{na}",
+                r = pair.origin.0[0],
+                g = pair.origin.0[1],
+                b = pair.origin.0[2],
+                a = pair.origin.0[3],
+                nr = pair.neighbor.0[0],
+                ng = pair.neighbor.0[1],
+                nb = pair.neighbor.0[2],
+                na = pair.neighbor.0[3],
+            )?;
+
+            match self.weighted {
+                true => writeln!(out, "\t{count}")?,
+                false => writeln!(out)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A Graphviz `graph` where each distinct color is a node and each
+/// undirected color adjacency is an edge.
+#[derive(Debug, Clone, Copy)]
+pub struct DotWriter;
+
+impl AdjacencyWriter for DotWriter {
+    fn write(&self, out: &mut dyn Write, pairs: &[(PixelPair, u64)]) -> io::Result<()> {
+        // The pair set is bidirectional; only emit each undirected edge
+        // once. `Rgba<u8>` has no `Ord` impl, so compare the inner channel
+        // arrays directly, matching the pattern `PixelPair::cmp` uses.
+        let edges: Vec<PixelPair> = pairs
+            .iter()
+            .map(|&(pair, _)| pair)
+            .filter(|pair| pair.origin.0 <= pair.neighbor.0)
+            .collect();
+
+        let nodes: HashSet<Rgba<u8>> = edges
+            .iter()
+            .flat_map(|pair| [pair.origin, pair.neighbor])
+            .collect();
+
+        writeln!(out, "graph couladj {{")?;
+        writeln!(out, "    node [style=filled];")?;
+
+        for color in nodes {
+            let id = hex_color(color);
+            writeln!(out, "    \"{id}\" [fillcolor=\"{id}\"];")?;
+        }
+
+        for pair in edges {
+            writeln!(
+                out,
+                "    \"{}\" -- \"{}\";",
+                hex_color(pair.origin),
+                hex_color(pair.neighbor)
+            )?;
+        }
+
+        writeln!(out, "}}")?;
+
+        Ok(())
+    }
+}
+
+/// Which `AdjacencyWriter` to use for the final adjacency dump.
+#[derive(Debug, Clone, Copy)]
+pub enum OutputFormat {
+    Tsv,
+    Dot,
+}
+
+impl OutputFormat {
+    pub fn writer(self, weighted: bool) -> Box<dyn AdjacencyWriter> {
+        match self {
+            OutputFormat::Tsv => Box::new(TsvWriter { weighted }),
+            OutputFormat::Dot => Box::new(DotWriter),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "tsv" => Ok(OutputFormat::Tsv),
+            "dot" => Ok(OutputFormat::Dot),
+            other => Err(format!(
+                "unrecognized output format {other:?}; expected \"tsv\" or \"dot\""
+            )),
+        }
+    }
+}